@@ -0,0 +1,182 @@
+//! Virtual memory areas (VMAs): a sorted-by-start-address list of mapped
+//! regions, replacing the page-aligned whole-region assumptions that
+//! `sys_mmap`/`sys_munmap` used to make directly against the page table.
+//!
+//! Keeping an explicit VMA per region (instead of only the page table)
+//! lets `munmap`/`mprotect` operate on arbitrary sub-ranges by splitting a
+//! VMA in two, and lets `mmap` defer actually allocating frames until the
+//! first page fault (lazy/demand paging).
+
+use super::{MapPermission, VirtAddr, VirtPageNum};
+use alloc::vec::Vec;
+
+/// What backs a VMA's pages.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum VmAreaBacking {
+    /// Anonymous memory, e.g. from `mmap(MAP_ANONYMOUS)` or the heap/stack.
+    Anonymous,
+    /// Backed by a file (not yet read in — demand-paged on fault).
+    File,
+}
+
+/// One contiguous mapped region of a task's address space.
+#[derive(Copy, Clone)]
+pub struct VmArea {
+    /// Inclusive start of the region.
+    pub start: VirtAddr,
+    /// Exclusive end of the region.
+    pub end: VirtAddr,
+    /// Page permissions shared by the whole region.
+    pub perm: MapPermission,
+    /// What backs the region's pages.
+    pub backing: VmAreaBacking,
+    /// Whether pages in this region have actually been allocated and
+    /// mapped yet, or are still waiting for their first page fault.
+    pub populated: bool,
+}
+
+impl VmArea {
+    /// Returns whether `addr` falls within `[start, end)`.
+    pub fn contains(&self, addr: VirtAddr) -> bool {
+        self.start <= addr && addr < self.end
+    }
+
+    fn mergeable_with(&self, other: &VmArea) -> bool {
+        self.perm == other.perm && self.backing == other.backing && self.end == other.start
+    }
+}
+
+/// A sorted-by-start-address list of non-overlapping [`VmArea`]s for one
+/// address space, supporting the split/merge operations `mmap`, `munmap`
+/// and `mprotect` need.
+#[derive(Default)]
+pub struct VmAreaList {
+    areas: Vec<VmArea>,
+}
+
+impl VmAreaList {
+    /// A fresh, empty list.
+    pub fn new() -> Self {
+        Self { areas: Vec::new() }
+    }
+
+    /// Binary-searches for the index of the (at most one) VMA containing
+    /// `addr`, if any.
+    pub fn find(&self, addr: VirtAddr) -> Option<usize> {
+        let idx = self
+            .areas
+            .partition_point(|area| area.start <= addr);
+        if idx > 0 && self.areas[idx - 1].contains(addr) {
+            Some(idx - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Inserts a new, initially unpopulated VMA, merging it with an
+    /// immediately adjacent VMA of identical permissions/backing if one
+    /// exists so the list stays compact.
+    pub fn insert(&mut self, mut area: VmArea) {
+        let idx = self.areas.partition_point(|a| a.start < area.start);
+        if idx > 0 && self.areas[idx - 1].mergeable_with(&area) {
+            area.start = self.areas[idx - 1].start;
+            self.areas.remove(idx - 1);
+        }
+        let idx = self.areas.partition_point(|a| a.start < area.start);
+        if idx < self.areas.len() && area.mergeable_with(&self.areas[idx]) {
+            area.end = self.areas[idx].end;
+            self.areas.remove(idx);
+        }
+        let idx = self.areas.partition_point(|a| a.start < area.start);
+        self.areas.insert(idx, area);
+    }
+
+    /// Removes `[start, end)` from the list, splitting any VMA that only
+    /// partially overlaps the removed range into the piece(s) that remain.
+    pub fn remove_range(&mut self, start: VirtAddr, end: VirtAddr) {
+        let mut i = 0;
+        while i < self.areas.len() {
+            let area = self.areas[i];
+            if area.end <= start || area.start >= end {
+                i += 1;
+                continue;
+            }
+            self.areas.remove(i);
+            if area.start < start {
+                self.areas.insert(
+                    i,
+                    VmArea {
+                        start: area.start,
+                        end: start,
+                        ..area
+                    },
+                );
+                i += 1;
+            }
+            if end < area.end {
+                self.areas.insert(
+                    i,
+                    VmArea {
+                        start: end,
+                        end: area.end,
+                        ..area
+                    },
+                );
+                i += 1;
+            }
+        }
+    }
+
+    /// Changes the permission of `[start, end)`, splitting any VMA whose
+    /// boundaries don't line up with the requested range so only the
+    /// requested sub-range is affected; the PTE flag rewrite itself is done
+    /// by the caller over the same range once this returns.
+    pub fn set_permission(&mut self, start: VirtAddr, end: VirtAddr, perm: MapPermission) {
+        let affected: Vec<VmArea> = self
+            .areas
+            .iter()
+            .copied()
+            .filter(|a| a.start < end && a.end > start)
+            .collect();
+        // `remove_range` already splits each affected area and reinserts
+        // whichever of its left/right remainder falls outside [start, end) -
+        // that's the whole point of passing it the untouched `affected`
+        // bounds below. Only the [lo, hi) slice with the new permission is
+        // ours to add back; reinserting the remainders here too (as a
+        // previous version of this function did) would duplicate them.
+        self.remove_range(start, end);
+        for area in affected {
+            let lo = core::cmp::max(area.start, start);
+            let hi = core::cmp::min(area.end, end);
+            self.insert(VmArea {
+                start: lo,
+                end: hi,
+                perm,
+                ..area
+            });
+        }
+    }
+
+    /// Marks the page containing `addr` as populated; called by the
+    /// page-fault handler once it has allocated and mapped the frame.
+    pub fn mark_populated(&mut self, addr: VirtAddr) {
+        if let Some(idx) = self.find(addr) {
+            self.areas[idx].populated = true;
+        }
+    }
+}
+
+/// Decides how the page-fault trap handler should respond to a fault at
+/// `addr`: allocate a frame if a lazily-mapped, not-yet-populated VMA
+/// legally covers it, or report that the access is illegal so the caller
+/// can kill the task. A fault on an already-`populated` VMA is never a
+/// lazy-mapping fault (the page is already backed) - reporting it as one
+/// would re-populate an already-mapped page on every repeat fault instead
+/// of surfacing the real protection violation that caused it.
+pub fn handle_lazy_fault(areas: &VmAreaList, addr: VirtAddr) -> Option<(VirtPageNum, MapPermission)> {
+    areas
+        .find(addr)
+        .map(|idx| &areas.areas[idx])
+        .filter(|area| !area.populated)
+        .map(|area| (addr.floor(), area.perm))
+}
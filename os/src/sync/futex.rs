@@ -0,0 +1,84 @@
+//! `futex(2)`-style fast userspace mutex support.
+//!
+//! Unlike `sys_mutex_*`/`sys_semaphore_*`, which always allocate a kernel
+//! object up front, a futex only traps into the kernel on contention: user
+//! code does the uncontended fast path with a single atomic instruction and
+//! falls back to `FUTEX_WAIT`/`FUTEX_WAKE` only when it needs to block or
+//! wake a waiter.
+
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+/// Wait for any bit of `val3`, i.e. don't filter wakeups by bitset.
+pub const FUTEX_BITSET_MATCH_ANY: u32 = 0xffff_ffff;
+
+pub const FUTEX_WAIT: usize = 0;
+pub const FUTEX_WAKE: usize = 1;
+pub const FUTEX_WAIT_BITSET: usize = 9;
+pub const FUTEX_WAKE_BITSET: usize = 10;
+
+struct Waiter {
+    task: Arc<TaskControlBlock>,
+    bitset: u32,
+}
+
+/// One wait queue per contended futex address, keyed by the *physical*
+/// address so that two processes sharing the backing page (e.g. via
+/// `CLONE_VM`) contend on the same bucket.
+#[derive(Default)]
+struct FutexTable {
+    buckets: BTreeMap<usize, Vec<Waiter>>,
+}
+
+lazy_static! {
+    static ref FUTEX_TABLE: Mutex<FutexTable> = Mutex::new(FutexTable::default());
+}
+
+/// Blocks the current task on `paddr` unless the word currently stored
+/// there differs from `val`, in which case the wait races the waker and
+/// must be retried by userspace (`EAGAIN`). Returns `0` on a normal wakeup.
+///
+/// The check and the enqueue must happen as one atomic step under
+/// `FUTEX_TABLE`'s lock: reading the word first and only locking afterward
+/// to enqueue leaves a window where a `FUTEX_WAKE` can land between the two
+/// and be missed entirely, since the waiter wasn't in any bucket yet to be
+/// woken, and then blocks forever on a value that already changed.
+pub fn futex_wait(paddr: usize, val: u32, bitset: u32) -> isize {
+    let task = current_task().unwrap();
+    let mut table = FUTEX_TABLE.lock();
+    let current_val = unsafe { *(paddr as *const u32) };
+    if current_val != val {
+        return -11; // EAGAIN
+    }
+    table.buckets.entry(paddr).or_default().push(Waiter {
+        task: Arc::clone(&task),
+        bitset,
+    });
+    drop(table);
+    block_current_and_run_next();
+    0
+}
+
+/// Wakes up to `max_count` waiters on `paddr` whose stored bitset ANDs
+/// non-zero with `bitset`, returning how many were actually woken.
+pub fn futex_wake(paddr: usize, max_count: u32, bitset: u32) -> isize {
+    let mut table = FUTEX_TABLE.lock();
+    let Some(waiters) = table.buckets.get_mut(&paddr) else {
+        return 0;
+    };
+    let mut woken = 0u32;
+    waiters.retain(|waiter| {
+        if woken < max_count && waiter.bitset & bitset != 0 {
+            wakeup_task(Arc::clone(&waiter.task));
+            woken += 1;
+            false
+        } else {
+            true
+        }
+    });
+    woken as isize
+}
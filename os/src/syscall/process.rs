@@ -1,6 +1,7 @@
 //! Process management syscalls
 //!
 use alloc::sync::Arc;
+use alloc::vec;
 
 use crate::{
     config::MAX_SYSCALL_NUM,
@@ -9,7 +10,8 @@ use crate::{
     task::{
         add_task, current_task, current_user_token, exit_current_and_run_next,
         suspend_current_and_run_next, TaskStatus,
-        mmap_current, munmap_current, get_task_info,
+        mmap_current, munmap_current, mprotect_current, get_task_info,
+        clone_flags::CloneFlags,
     },
     timer::get_time_us,
 };
@@ -58,17 +60,78 @@ pub fn sys_getpid() -> isize {
 /// fork
 pub fn sys_fork() -> isize {
     trace!("kernel:pid[{}] sys_fork", current_task().unwrap().pid.0);
+    sys_clone(CloneFlags::empty().bits(), 0)
+}
+
+/// clone: generalizes `sys_fork` with a `CloneFlags` bitset. `CLONE_VM` and
+/// `CLONE_THREAD` together create a new thread inside the caller's process
+/// (sharing its address space) instead of duplicating the whole process;
+/// `stack` is then the user stack pointer the new thread starts on.
+/// `CLONE_FILES`/`CLONE_FS` additionally share the fd table / fs info with
+/// a freshly forked process.
+pub fn sys_clone(flags: usize, stack: usize) -> isize {
+    trace!("kernel:pid[{}] sys_clone flags={:#x}", current_task().unwrap().pid.0, flags);
+    let flags = CloneFlags::from_bits_truncate(flags);
     let current_task = current_task().unwrap();
-    let new_task = current_task.fork();
-    let new_pid = new_task.pid.0;
-    // modify trap context of new_task, because it returns immediately after switching
-    let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
-    // we do not have to move to next instruction since we have done it before
-    // for child process, fork returns 0
-    trap_cx.x[10] = 0;
-    // add new task to scheduler
-    add_task(new_task);
-    new_pid as isize
+
+    if flags.contains(CloneFlags::CLONE_VM) && flags.contains(CloneFlags::CLONE_THREAD) {
+        // New thread: shares memory_set, gets its own kernel stack/tid and a
+        // TrapContext pointing at the caller-supplied user stack. It is
+        // registered into the existing process's `tasks` vector, and the
+        // deadlock-detection matrices (mutex_allocation, semaphore_need, ...)
+        // each grow a row *at* the new tid.
+        //
+        // `tid`s get recycled (a freed tid leaves a `None` gap in `tasks`),
+        // so the new tid is not necessarily `tasks.len()` before insertion -
+        // index by `tid` explicitly rather than assuming a `push` lands in
+        // the right slot, or these matrices (indexed by tid everywhere else)
+        // would desynchronize from `tasks`.
+        let process = current_task.process.upgrade().unwrap();
+        let new_task = current_task.clone_thread(stack);
+        {
+            let mut new_inner = new_task.inner_exclusive_access();
+            new_inner.priority = current_task.inner_exclusive_access().priority;
+            new_inner.stride = 0;
+            new_inner.stride_step = crate::task::stride::stride_step(new_inner.priority);
+        }
+        let new_tid = new_task.inner_exclusive_access().res.as_ref().unwrap().tid;
+        let mut process_inner = process.inner_exclusive_access();
+        let mutex_count = process_inner.mutex_list.len();
+        let sem_count = process_inner.semaphore_list.len();
+        for matrix in [
+            &mut process_inner.mutex_allocation,
+            &mut process_inner.mutex_need,
+        ] {
+            while matrix.len() <= new_tid {
+                matrix.push(vec![0; mutex_count]);
+            }
+        }
+        for matrix in [
+            &mut process_inner.semaphore_allocation,
+            &mut process_inner.semaphore_need,
+        ] {
+            while matrix.len() <= new_tid {
+                matrix.push(vec![0; sem_count]);
+            }
+        }
+        drop(process_inner);
+        add_task(Arc::clone(&new_task));
+        new_tid as isize
+    } else {
+        // New process: copy-on-write the address space (unless CLONE_VM is
+        // set), optionally sharing the fd table (CLONE_FILES) or fs info
+        // (CLONE_FS) rather than copying them.
+        let new_task = current_task.fork_with_flags(flags);
+        let new_pid = new_task.pid.0;
+        // modify trap context of new_task, because it returns immediately after switching
+        let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
+        // we do not have to move to next instruction since we have done it before
+        // for child process, fork returns 0
+        trap_cx.x[10] = 0;
+        // add new task to scheduler
+        add_task(new_task);
+        new_pid as isize
+    }
 }
 
 /// exec
@@ -174,6 +237,13 @@ pub fn sys_mmap(_start: usize, _len: usize, _port: usize) -> isize {
         return -1
     }
 
+    let process = current_task().unwrap().process.upgrade().unwrap();
+    let as_limit = process.inner_exclusive_access().rlimits[RLIMIT_AS].cur;
+    if process.inner_exclusive_access().memory_set.mapped_bytes() + _len > as_limit {
+        debug!("Map failed: would exceed RLIMIT_AS");
+        return -1
+    }
+
     let end_va: VirtAddr = VirtAddr(_start + _len);
     mmap_current(start_va, end_va, _port)
 }
@@ -194,9 +264,35 @@ pub fn sys_munmap(_start: usize, _len: usize) -> isize {
     munmap_current(start_va, end_va)
 }
 
+/// Change the permission of an existing mapping. Unlike `sys_mmap`/
+/// `sys_munmap`, the affected range need not line up with a whole VMA: the
+/// VMA list splits/merges the covering area(s) so only `[start, start+len)`
+/// has its PTE flags rewritten.
+pub fn sys_mprotect(_start: usize, _len: usize, _prot: usize) -> isize {
+    trace!("kernel:pid[{}] sys_mprotect", current_task().unwrap().pid.0);
+    let start_va: VirtAddr = _start.into();
+    if !start_va.aligned() {
+        debug!("mprotect failed: address not aligned");
+        return -1
+    }
+    if _prot & !0x7 != 0 {
+        return -1
+    }
+    let end_va: VirtAddr = VirtAddr(_start + _len);
+    mprotect_current(start_va, end_va, _prot)
+}
+
 /// change data segment size
 pub fn sys_sbrk(size: i32) -> isize {
     trace!("kernel:pid[{}] sys_sbrk", current_task().unwrap().pid.0);
+    if size > 0 {
+        let process = current_task().unwrap().process.upgrade().unwrap();
+        let as_limit = process.inner_exclusive_access().rlimits[RLIMIT_AS].cur;
+        if process.inner_exclusive_access().memory_set.mapped_bytes() + size as usize > as_limit {
+            debug!("sbrk failed: would exceed RLIMIT_AS");
+            return -1;
+        }
+    }
     if let Some(old_brk) = current_task().unwrap().change_program_brk(size) {
         old_brk as isize
     } else {
@@ -239,7 +335,11 @@ pub fn sys_set_priority(_prio: isize) -> isize {
     );
     if _prio >= 2 {
         let task = current_task().unwrap();
-        task.inner_exclusive_access().priority = _prio as usize;
+        let mut inner = task.inner_exclusive_access();
+        inner.priority = _prio as usize;
+        // Re-derive the stride increment immediately so the new priority
+        // takes effect starting from the task's next dispatch.
+        inner.stride_step = crate::task::stride::stride_step(_prio as usize);
         _prio
     }
     else {
@@ -248,8 +348,114 @@ pub fn sys_set_priority(_prio: isize) -> isize {
 }
 
 
+/// Resource index for `sys_setrlimit`/`sys_getrlimit`: max open file
+/// descriptors.
+pub const RLIMIT_NOFILE: usize = 0;
+/// Resource index: max address-space size in bytes.
+pub const RLIMIT_AS: usize = 1;
+/// Resource index: max user stack size in bytes.
+pub const RLIMIT_STACK: usize = 2;
+/// Number of `RLIMIT_*` resources this kernel tracks.
+pub const RLIMIT_COUNT: usize = 3;
+
+/// Soft/hard limits for one `RLIMIT_*` resource, as used by `getrlimit(2)`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct RLimit {
+    /// The limit enforced today; may be raised up to `max` by the process.
+    pub cur: usize,
+    /// The ceiling `cur` can be raised to.
+    pub max: usize,
+}
+
+/// Resource-usage counters returned by `sys_getrusage`, mirroring the
+/// fields `getrusage(2)` callers typically read.
+#[repr(C)]
+pub struct RUsage {
+    /// Time spent executing user code.
+    pub utime: TimeVal,
+    /// Time spent executing on this task's behalf in the kernel.
+    pub stime: TimeVal,
+    /// Peak resident set size, in pages.
+    pub maxrss: usize,
+    /// Voluntary context switches.
+    pub nvcsw: usize,
+    /// Involuntary context switches.
+    pub nivcsw: usize,
+}
+
+/// `who` value selecting the calling task in `sys_getrusage`, the only value
+/// it accepts. Counters are kept per task, so the result covers the calling
+/// task alone, not the other tasks of its process.
+pub const RUSAGE_SELF: isize = 0;
+
+/// Fill `usage` with the calling task's accumulated CPU-time and
+/// context-switch counters. The destination may straddle a page boundary,
+/// so each field is written through the same `v_to_p` translation used by
+/// `sys_get_time` rather than via a single raw pointer dereference.
+pub fn sys_getrusage(who: isize, usage: *mut RUsage) -> isize {
+    trace!("kernel:pid[{}] sys_getrusage", current_task().unwrap().pid.0);
+    if who != RUSAGE_SELF {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let snapshot = RUsage {
+        utime: TimeVal {
+            sec: inner.utime_us / 1_000_000,
+            usec: inner.utime_us % 1_000_000,
+        },
+        stime: TimeVal {
+            sec: inner.stime_us / 1_000_000,
+            usec: inner.stime_us % 1_000_000,
+        },
+        maxrss: inner.maxrss_pages,
+        nvcsw: inner.nvcsw,
+        nivcsw: inner.nivcsw,
+    };
+    drop(inner);
+    unsafe {
+        *v_to_p(core::ptr::addr_of!((*usage).utime.sec)) = snapshot.utime.sec;
+        *v_to_p(core::ptr::addr_of!((*usage).utime.usec)) = snapshot.utime.usec;
+        *v_to_p(core::ptr::addr_of!((*usage).stime.sec)) = snapshot.stime.sec;
+        *v_to_p(core::ptr::addr_of!((*usage).stime.usec)) = snapshot.stime.usec;
+        *v_to_p(core::ptr::addr_of!((*usage).maxrss)) = snapshot.maxrss;
+        *v_to_p(core::ptr::addr_of!((*usage).nvcsw)) = snapshot.nvcsw;
+        *v_to_p(core::ptr::addr_of!((*usage).nivcsw)) = snapshot.nivcsw;
+    }
+    0
+}
+
+/// Fetch the calling process's current soft/hard limit for `resource`.
+pub fn sys_getrlimit(resource: usize, rlim: *mut RLimit) -> isize {
+    trace!("kernel:pid[{}] sys_getrlimit resource={}", current_task().unwrap().pid.0, resource);
+    if resource >= RLIMIT_COUNT {
+        return -1;
+    }
+    let process = current_task().unwrap().process.upgrade().unwrap();
+    let limit = process.inner_exclusive_access().rlimits[resource];
+    *translated_refmut(current_user_token(), rlim) = limit;
+    0
+}
+
+/// Set the calling process's limit for `resource`, rejecting any attempt to
+/// raise `cur` above `max`.
+pub fn sys_setrlimit(resource: usize, rlim: *const RLimit) -> isize {
+    trace!("kernel:pid[{}] sys_setrlimit resource={}", current_task().unwrap().pid.0, resource);
+    if resource >= RLIMIT_COUNT {
+        return -1;
+    }
+    let requested = *translated_refmut(current_user_token(), rlim as *mut RLimit);
+    if requested.cur > requested.max {
+        return -1;
+    }
+    let process = current_task().unwrap().process.upgrade().unwrap();
+    process.inner_exclusive_access().rlimits[resource] = requested;
+    0
+}
+
 /// 虚拟地址转换成物理地址
-fn v_to_p<T>(user_va: *const T) -> *mut T {
+pub(crate) fn v_to_p<T>(user_va: *const T) -> *mut T {
     // 获取当前进程页表
     let page_table = PageTable::from_token(current_user_token());
     // 计算出vpn
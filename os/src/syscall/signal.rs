@@ -0,0 +1,91 @@
+//! Signal-related syscalls.
+
+use crate::mm::translated_refmut;
+use crate::task::current_task;
+use crate::task::signal::{SigAction, SigSet, MAX_SIG, SIGKILL, SIGSTOP};
+
+/// `sigprocmask` semantics for combining a new set with the current mask.
+const SIG_BLOCK: usize = 0;
+const SIG_UNBLOCK: usize = 1;
+const SIG_SETMASK: usize = 2;
+
+/// Install a new action for `signum`, returning the previous one through
+/// `oldact` if it is non-null. `SIGKILL`/`SIGSTOP` cannot be given a handler.
+pub fn sys_sigaction(signum: usize, act: *const SigAction, oldact: *mut SigAction) -> isize {
+    trace!("kernel:pid[{}] sys_sigaction", current_task().unwrap().pid.0);
+    if signum == 0 || signum > MAX_SIG || signum == SIGKILL || signum == SIGSTOP {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let process = task.process.upgrade().unwrap();
+    let token = crate::task::current_user_token();
+    let mut process_inner = process.inner_exclusive_access();
+    if !oldact.is_null() {
+        *translated_refmut(token, oldact) = process_inner.sig_actions[signum];
+    }
+    if !act.is_null() {
+        process_inner.sig_actions[signum] = *translated_refmut(token, act as *mut SigAction);
+    }
+    0
+}
+
+/// Fetch and/or update the calling task's blocked-signal set.
+pub fn sys_sigprocmask(how: usize, set: *const SigSet, oldset: *mut SigSet) -> isize {
+    trace!("kernel:pid[{}] sys_sigprocmask", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let token = crate::task::current_user_token();
+    let mut inner = task.inner_exclusive_access();
+    if !oldset.is_null() {
+        *translated_refmut(token, oldset) = inner.sig_mask;
+    }
+    if !set.is_null() {
+        let requested = *translated_refmut(token, set as *mut SigSet);
+        inner.sig_mask = match how {
+            SIG_BLOCK => SigSet(inner.sig_mask.0 | requested.0),
+            SIG_UNBLOCK => SigSet(inner.sig_mask.0 & !requested.0),
+            SIG_SETMASK => requested,
+            _ => return -1,
+        };
+        // SIGKILL and SIGSTOP can never actually be blocked.
+        inner.sig_mask.remove(SIGKILL);
+        inner.sig_mask.remove(SIGSTOP);
+    }
+    0
+}
+
+/// Raise `signum` against the process identified by `pid`, marking it
+/// pending on that process's first task.
+pub fn sys_kill(pid: usize, signum: usize) -> isize {
+    trace!("kernel:pid[{}] sys_kill target={}", current_task().unwrap().pid.0, pid);
+    if signum == 0 || signum > crate::task::signal::MAX_SIG {
+        return -1;
+    }
+    if let Some(process) = crate::task::pid2process(pid) {
+        let process_inner = process.inner_exclusive_access();
+        if let Some(task) = process_inner.tasks.get(0).and_then(|t| t.clone()) {
+            task.inner_exclusive_access().sig_pending.add(signum);
+            0
+        } else {
+            -1
+        }
+    } else {
+        -1
+    }
+}
+
+/// Return from a signal handler: restore the `TrapContext` kept in the
+/// task's `signal_saved_cx` when the handler was entered and resume there.
+/// The copy pushed onto the user stack is not read back.
+pub fn sys_sigreturn() -> isize {
+    trace!("kernel:pid[{}] sys_sigreturn", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if let Some(saved_cx) = inner.signal_saved_cx.take() {
+        *inner.get_trap_cx() = saved_cx;
+        // The syscall return path writes our return value into `a0`;
+        // returning the restored `a0` keeps the interrupted code's register.
+        inner.get_trap_cx().x[10] as isize
+    } else {
+        -1
+    }
+}
@@ -1,8 +1,12 @@
+use super::process::v_to_p;
+use crate::sync::futex::{futex_wait, futex_wake, FUTEX_BITSET_MATCH_ANY, FUTEX_WAIT,
+    FUTEX_WAIT_BITSET, FUTEX_WAKE, FUTEX_WAKE_BITSET};
 use crate::sync::{Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore};
 use crate::task::{block_current_and_run_next, current_process, current_task};
 use crate::timer::{add_timer, get_time_ms};
 use alloc::sync::Arc;
 use alloc::vec;
+use alloc::vec::Vec;
 /// sleep syscall
 pub fn sys_sleep(ms: usize) -> isize {
     trace!(
@@ -93,54 +97,22 @@ pub fn sys_mutex_lock(mutex_id: usize) -> isize {
     let process = current_process();
     let mut process_inner = process.inner_exclusive_access();
 
-    // Need[i,j] = Max[i,j] - mutex_allocation[i, j]
-    // 如果需要分配
-    // Available[j] = Available[j] - Request[i,j];
-    // Allocation[i,j] = Allocation[i,j] + Request[i,j];
-    // Need[i,j] = Need[i,j] - Request[i,j];
-    if process_inner.deadlock_detect {
+    if let Some(strategy) = process_inner.deadlock_strategy {
         let tid = current_task().unwrap().inner_exclusive_access().res.as_ref().unwrap().tid;
+        // Record the request edge up front: this thread is now waiting on
+        // `mutex_id`. It stays recorded until the lock is actually granted
+        // below, so it is visible to any other thread's deadlock check too.
         process_inner.mutex_need[tid][mutex_id] += 1;
-        let task_count = process_inner.tasks.len();
-        // 安全性算法
-        // 设置两个向量:工作向量Work，表示操作系统可提供给线程继续运行所需的各类资源数目，
-        // 它含有m个元素，初始时，Work = Available；结束向量Finish，表示系统是否有足够的资源分配给线程，使之运行完成。
-        // 初始时 Finish[0..n-1] = false，表示所有线程都没结束；当有足够资源分配给线程时，设置Finish[i] = true
-        // 第一步
-        let mut finish = vec![false; task_count];
-        let mut work = process_inner.mutex_available.clone();
-        loop {
-            let mut is_safe = true;
-            for i in 0..process_inner.mutex_need.len() {
-                if !finish[i] { 
-                    is_safe = false; 
-                    break;
-                }
-            }
 
-            // 第四步
-            // 如果finish里面全是true说明安全了
-            if is_safe { break; }
-            
-            for i in 0..process_inner.mutex_need.len() {
-                //第二步
-                if process_inner.mutex_need[tid][i] <= work[i] {
-                    // 第三步
-                    work[i] += process_inner.mutex_allocation[tid][i];
-                    finish[i] = true;
-                    is_safe = true;
-                } else { is_safe = false; break; }
-            } 
-            if !is_safe { break; }   
-              
-        }
+        let would_deadlock = match strategy {
+            DeadlockStrategy::Graph => mutex_wait_graph_has_cycle(&process_inner, tid),
+            DeadlockStrategy::Banker => mutex_banker_is_unsafe(&process_inner),
+        };
 
-        for i in finish {
-            if !i {
-                return -0xDEAD;
-            }
-        }     
-       
+        if would_deadlock {
+            process_inner.mutex_need[tid][mutex_id] -= 1;
+            return -0xDEAD;
+        }
     }
 
     let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
@@ -152,7 +124,13 @@ pub fn sys_mutex_lock(mutex_id: usize) -> isize {
     let mut process_inner = process.inner_exclusive_access();
     let tid = current_task().unwrap().inner_exclusive_access().res.as_ref().unwrap().tid;
     process_inner.mutex_available[mutex_id] -= 1;
-    process_inner.mutex_need[tid][mutex_id] -= 1;
+    // Only undoes the request edge recorded above, which only happened when
+    // deadlock detection was on at request time; with it off (the default),
+    // `mutex_need[tid][mutex_id]` was never incremented, so unconditionally
+    // decrementing here would underflow. `saturating_sub` makes this correct
+    // whether or not detection was on, and even if it was toggled in between.
+    process_inner.mutex_need[tid][mutex_id] =
+        process_inner.mutex_need[tid][mutex_id].saturating_sub(1);
     process_inner.mutex_allocation[tid][mutex_id] += 1;
 
     0
@@ -279,56 +257,46 @@ pub fn sys_semaphore_down(sem_id: usize) -> isize {
     let mut process_inner = process.inner_exclusive_access();
     let sem = Arc::clone(process_inner.semaphore_list[sem_id].as_ref().unwrap());
 
-        // Need[i,j] = Max[i,j] - mutex_allocation[i, j]
-    // 如果需要分配
-    // Available[j] = Available[j] - Request[i,j];
-    // Allocation[i,j] = Allocation[i,j] + Request[i,j];
-    // Need[i,j] = Need[i,j] - Request[i,j];
-    if process_inner.deadlock_detect {
+    // Semaphores can hold more than one instance at once, so single-instance
+    // cycle detection doesn't apply here: regardless of the selected
+    // strategy, multi-instance resources are always checked with the (fixed)
+    // Banker's safety algorithm.
+    //
+    // Need[i,j] = Max[i,j] - semaphore_allocation[i, j]
+    // Work starts as Available; Finish[i] becomes true once every resource
+    // column satisfies Need[i][j] <= Work[j]. The outer loop keeps scanning
+    // until a full pass makes no further progress.
+    if process_inner.deadlock_strategy.is_some() {
         let tid = current_task().unwrap().inner_exclusive_access().res.as_ref().unwrap().tid;
         process_inner.semaphore_need[tid][sem_id] += 1;
         let task_count = process_inner.tasks.len();
-        
-        // 安全性算法
-        // 设置两个向量:工作向量Work，表示操作系统可提供给线程继续运行所需的各类资源数目，
-        // 它含有m个元素，初始时，Work = Available；结束向量Finish，表示系统是否有足够的资源分配给线程，使之运行完成。
-        // 初始时 Finish[0..n-1] = false，表示所有线程都没结束；当有足够资源分配给线程时，设置Finish[i] = true
-        // 第一步
+
         let mut finish = vec![false; task_count];
         let mut work = process_inner.semaphore_available.clone();
-        loop {  
-            let mut is_safe = true;      
+        loop {
+            let mut made_progress = false;
             for i in 0..process_inner.semaphore_need.len() {
                 if !finish[i] {
-                    let mut is_safe_2 = true;
-                    for j in 0..work.len() {
-                        //第二步
-                        if process_inner.semaphore_need[i][j] > work[j] {
-                            is_safe_2 = false; 
-                            break; 
-                        } 
-                    }
-                    if is_safe_2 {
-                        is_safe = false;
+                    let can_finish = (0..work.len())
+                        .all(|j| process_inner.semaphore_need[i][j] <= work[j]);
+                    if can_finish {
+                        made_progress = true;
                         finish[i] = true;
                         for j in 0..work.len() {
-                            // 第三步
                             work[j] += process_inner.semaphore_allocation[i][j];
                         }
                     }
                 }
             }
+            if !made_progress {
+                break;
+            }
+        }
 
-            // 第四步
-            // 如果finish里面全是true说明安全了
-            if is_safe { break; }
+        if finish.iter().any(|done| !done) {
+            process_inner.semaphore_need[tid][sem_id] -= 1;
+            return -0xDEAD;
         }
-        
-        for i in finish {
-            if !i {
-                return -0xDEAD;
-            }
-        }        
     }
 
     drop(process_inner);
@@ -416,21 +384,161 @@ pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
     condvar.wait(mutex);
     0
 }
+/// Which algorithm `sys_mutex_lock` uses to decide whether granting a lock
+/// would deadlock. Semaphores always use [`DeadlockStrategy::Banker`]
+/// regardless of this choice, since cycle detection over a wait-for graph
+/// is only exact for single-instance resources.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum DeadlockStrategy {
+    /// Build a resource-allocation graph (threads and mutexes as nodes) and
+    /// check whether granting the lock would close a cycle.
+    Graph,
+    /// The Banker's algorithm safety check.
+    Banker,
+}
+
+/// Treats the wait-for graph as nodes `0..task_count` for threads and
+/// `task_count..task_count+mutex_count` for mutexes, with a thread->mutex
+/// request edge wherever `mutex_need` is non-zero and a mutex->thread
+/// assignment edge wherever `mutex_allocation` is non-zero (both persist
+/// across calls, so every other blocked thread's edges are visible too).
+/// Returns whether a DFS from `requesting_tid`, coloring nodes
+/// white/gray/black, walks back into a node still on the stack (gray) -
+/// i.e. whether a cycle is reachable from the new request.
+fn mutex_wait_graph_has_cycle(
+    process_inner: &crate::task::ProcessControlBlockInner,
+    requesting_tid: usize,
+) -> bool {
+    let task_count = process_inner.tasks.len();
+    let mutex_count = process_inner.mutex_list.len();
+    let node_count = task_count + mutex_count;
+
+    #[derive(Copy, Clone, PartialEq)]
+    enum Color { White, Gray, Black }
+
+    let mut color = vec![Color::White; node_count];
+
+    fn visit(
+        node: usize,
+        color: &mut Vec<Color>,
+        task_count: usize,
+        mutex_count: usize,
+        process_inner: &crate::task::ProcessControlBlockInner,
+    ) -> bool {
+        color[node] = Color::Gray;
+        let neighbors: Vec<usize> = if node < task_count {
+            let tid = node;
+            (0..mutex_count)
+                .filter(|&m| process_inner.mutex_need[tid][m] > 0)
+                .map(|m| task_count + m)
+                .collect()
+        } else {
+            let mutex_id = node - task_count;
+            (0..task_count)
+                .filter(|&t| process_inner.mutex_allocation[t][mutex_id] > 0)
+                .collect()
+        };
+        for next in neighbors {
+            match color[next] {
+                Color::Gray => return true,
+                Color::White => {
+                    if visit(next, color, task_count, mutex_count, process_inner) {
+                        return true;
+                    }
+                }
+                Color::Black => {}
+            }
+        }
+        color[node] = Color::Black;
+        false
+    }
+
+    visit(requesting_tid, &mut color, task_count, mutex_count, process_inner)
+}
+
+/// The (fixed) Banker's safety algorithm applied to mutexes: since every
+/// mutex is single-instance, `Need[i][j]` and `Allocation[i][j]` are always
+/// `0` or `1`, but the check is otherwise identical to the semaphore one -
+/// `Finish[i]` is set only once every resource column satisfies
+/// `Need[i][j] <= Work[j]`, and the outer loop runs until a pass makes no
+/// further progress.
+fn mutex_banker_is_unsafe(process_inner: &crate::task::ProcessControlBlockInner) -> bool {
+    let task_count = process_inner.tasks.len();
+    let mut finish = vec![false; task_count];
+    let mut work = process_inner.mutex_available.clone();
+    loop {
+        let mut made_progress = false;
+        for i in 0..process_inner.mutex_need.len() {
+            if !finish[i] {
+                let can_finish =
+                    (0..work.len()).all(|j| process_inner.mutex_need[i][j] <= work[j]);
+                if can_finish {
+                    made_progress = true;
+                    finish[i] = true;
+                    for j in 0..work.len() {
+                        work[j] += process_inner.mutex_allocation[i][j];
+                    }
+                }
+            }
+        }
+        if !made_progress {
+            break;
+        }
+    }
+    finish.iter().any(|done| !done)
+}
+
 /// enable deadlock detection syscall
 ///
-/// YOUR JOB: Implement deadlock detection, but might not all in this syscall
-pub fn sys_enable_deadlock_detect(_enabled: usize) -> isize {
-    trace!("kernel: sys_enable_deadlock_detect NOT IMPLEMENTED");
+/// `strategy` selects the algorithm used by `sys_mutex_lock`: `0` disables
+/// detection, `1` selects the Banker's algorithm, `2` selects wait-for-graph
+/// cycle detection.
+pub fn sys_enable_deadlock_detect(strategy: usize) -> isize {
+    trace!("kernel: sys_enable_deadlock_detect strategy={}", strategy);
     let current = current_process();
     let mut process_inner = current.inner_exclusive_access();
-    if _enabled == 1{
-        process_inner.deadlock_detect = true
-    } else {
-        process_inner.deadlock_detect = false
-    }
+    process_inner.deadlock_strategy = match strategy {
+        0 => None,
+        1 => Some(DeadlockStrategy::Banker),
+        2 => Some(DeadlockStrategy::Graph),
+        _ => return -1,
+    };
     0
 }
 
+/// futex syscall: a scalable alternative to `sys_mutex_*`/`sys_semaphore_*`
+/// where the kernel is only involved on contention. `uaddr2`/`timeout` are
+/// accepted for ABI compatibility but unused by the operations below.
+pub fn sys_futex(
+    uaddr: *mut u32,
+    op: usize,
+    val: u32,
+    _timeout: usize,
+    _uaddr2: *mut u32,
+    val3: u32,
+) -> isize {
+    trace!(
+        "kernel:pid[{}] tid[{}] sys_futex op={}",
+        current_task().unwrap().process.upgrade().unwrap().getpid(),
+        current_task()
+            .unwrap()
+            .inner_exclusive_access()
+            .res
+            .as_ref()
+            .unwrap()
+            .tid,
+        op
+    );
+    let paddr = v_to_p(uaddr) as usize;
+    match op {
+        FUTEX_WAIT => futex_wait(paddr, val, FUTEX_BITSET_MATCH_ANY),
+        FUTEX_WAIT_BITSET => futex_wait(paddr, val, val3),
+        FUTEX_WAKE => futex_wake(paddr, val, FUTEX_BITSET_MATCH_ANY),
+        FUTEX_WAKE_BITSET => futex_wake(paddr, val, val3),
+        _ => -1,
+    }
+}
+
 //pub fn is_deadlocked() {
 
 // }
\ No newline at end of file
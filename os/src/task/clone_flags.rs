@@ -0,0 +1,18 @@
+//! Flags accepted by `sys_clone`, modeled after DragonOS's `CloneFlags`.
+
+bitflags::bitflags! {
+    /// Controls what a `sys_clone`d task shares with its creator instead of
+    /// copying. An empty set reproduces plain `fork()` semantics.
+    pub struct CloneFlags: usize {
+        /// Share the caller's address space (`memory_set`) instead of
+        /// copy-on-write duplicating it.
+        const CLONE_VM = 1 << 8;
+        /// Share the caller's open file descriptor table.
+        const CLONE_FILES = 1 << 10;
+        /// Place the new task in the caller's process as an additional
+        /// thread rather than spawning a new process.
+        const CLONE_THREAD = 1 << 16;
+        /// Share the caller's filesystem information (cwd, root, umask).
+        const CLONE_FS = 1 << 9;
+    }
+}
@@ -0,0 +1,193 @@
+//! POSIX-style signal types shared by the task/process control blocks.
+//!
+//! Modeled after the `ipc::signal` module in DragonOS: a process carries a
+//! table of [`SigAction`]s, and every task carries its own pending set and
+//! blocked (masked) set so that signals can be targeted at a single thread.
+
+/// Number of signals supported, numbered `1..=MAX_SIG`.
+pub const MAX_SIG: usize = 64;
+
+/// `SIGKILL` terminates the task unconditionally and cannot be caught,
+/// blocked, or ignored.
+pub const SIGKILL: usize = 9;
+/// `SIGSTOP` cannot be caught or blocked. There is no stopped task state:
+/// its default action ([`default_action`]) ignores it.
+pub const SIGSTOP: usize = 19;
+/// `SIGSEGV` is delivered on an illegal memory access.
+pub const SIGSEGV: usize = 11;
+
+/// A 64-bit bitmask over signal numbers `1..=64` (bit `n - 1` is signal `n`).
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct SigSet(pub u64);
+
+impl SigSet {
+    /// The empty set.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns whether `signum` is a member of this set.
+    pub fn contains(&self, signum: usize) -> bool {
+        signum >= 1 && signum <= MAX_SIG && self.0 & (1u64 << (signum - 1)) != 0
+    }
+
+    /// Adds `signum` to the set.
+    pub fn add(&mut self, signum: usize) {
+        if signum >= 1 && signum <= MAX_SIG {
+            self.0 |= 1u64 << (signum - 1);
+        }
+    }
+
+    /// Removes `signum` from the set.
+    pub fn remove(&mut self, signum: usize) {
+        if signum >= 1 && signum <= MAX_SIG {
+            self.0 &= !(1u64 << (signum - 1));
+        }
+    }
+
+    /// Returns the lowest-numbered signal present in `self` but not masked
+    /// by `blocked`, if any. `SIGKILL`/`SIGSTOP` are always reported even if
+    /// `blocked` claims to mask them, since they are non-maskable.
+    pub fn first_deliverable(&self, blocked: &SigSet) -> Option<usize> {
+        for signum in 1..=MAX_SIG {
+            if self.contains(signum)
+                && (signum == SIGKILL || signum == SIGSTOP || !blocked.contains(signum))
+            {
+                return Some(signum);
+            }
+        }
+        None
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags controlling how a registered handler is invoked, mirroring the
+    /// subset of `sigaction(2)`'s `sa_flags` this kernel understands.
+    #[derive(Default)]
+    pub struct SigActionFlags: u32 {
+        /// Restart interrupted syscalls after the handler returns.
+        const SA_RESTART = 1 << 0;
+    }
+}
+
+/// Disposition for a single signal, as installed by `sys_sigaction`.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SigAction {
+    /// User-space address of the handler, or `0`/`1` for `SIG_DFL`/`SIG_IGN`.
+    pub handler: usize,
+    /// Additional signals blocked while this handler is running.
+    pub mask: SigSet,
+    /// `sa_flags`.
+    pub flags: SigActionFlags,
+}
+
+impl SigAction {
+    /// The default disposition: no handler installed.
+    pub const fn new() -> Self {
+        Self {
+            handler: 0,
+            mask: SigSet::empty(),
+            flags: SigActionFlags::empty(),
+        }
+    }
+}
+
+impl Default for SigAction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What to do with a signal that has no user handler installed.
+pub enum SigDefault {
+    /// Terminate the receiving task's process.
+    Terminate,
+    /// Do nothing.
+    Ignore,
+}
+
+/// Looks up the default action for signals without a registered handler.
+/// `SIGKILL` and `SIGSEGV` terminate the process; everything else not
+/// explicitly handled elsewhere is ignored.
+pub fn default_action(signum: usize) -> SigDefault {
+    match signum {
+        SIGKILL | SIGSEGV => SigDefault::Terminate,
+        _ => SigDefault::Ignore,
+    }
+}
+
+/// The signal mask that was in effect before delivery, pushed onto the user
+/// stack directly ahead of a saved copy of the user `TrapContext` so
+/// `sys_sigreturn` can restore the interrupted execution state.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SignalUserContext {
+    /// The mask to restore once the handler returns.
+    pub saved_mask: SigSet,
+}
+
+/// What the trap-return path should do about the calling task's pending
+/// signals, as decided by [`resolve_delivery`].
+pub enum SignalDisposition {
+    /// Nothing to act on right now: either nothing is pending, everything
+    /// pending is currently blocked, or the signal taken was set to
+    /// `SIG_IGN` and has been discarded.
+    None,
+    /// `signum` has the default disposition (`SIG_DFL`); the caller
+    /// should apply `action` (terminating the process, or doing nothing).
+    Default { signum: usize, action: SigDefault },
+    /// Enter the registered handler for `signum`. The caller still has to:
+    /// write a [`SignalUserContext`] (carrying `prev_mask`) immediately
+    /// followed by a saved copy of the current `TrapContext` at `new_sp`,
+    /// install `new_mask` as the task's blocked set, and resume user
+    /// execution at `handler` with `sepc = handler` and `x[10] = signum`.
+    EnterHandler {
+        signum: usize,
+        handler: usize,
+        new_sp: usize,
+        prev_mask: SigSet,
+        new_mask: SigSet,
+    },
+}
+
+/// Picks the next signal (if any) deliverable to a task and removes it from
+/// `pending`, since it is being acted on now either way. `actions` is the
+/// owning process's `sig_actions` table, indexed by signal number;
+/// `user_sp` is the stack pointer the trap-return path would otherwise
+/// resume at. Does not itself touch the trap context or page table; the
+/// caller, which has both in hand, does the writes described under
+/// [`SignalDisposition::EnterHandler`].
+pub fn resolve_delivery(
+    pending: &mut SigSet,
+    blocked: &SigSet,
+    actions: &[SigAction],
+    user_sp: usize,
+) -> SignalDisposition {
+    let Some(signum) = pending.first_deliverable(blocked) else {
+        return SignalDisposition::None;
+    };
+    pending.remove(signum);
+    let action = actions[signum];
+    match action.handler {
+        0 => SignalDisposition::Default {
+            signum,
+            action: default_action(signum),
+        },
+        1 => SignalDisposition::None,
+        handler => {
+            let cx_size = core::mem::size_of::<SignalUserContext>()
+                + core::mem::size_of::<crate::trap::TrapContext>();
+            let new_sp = (user_sp.saturating_sub(cx_size)) & !0xf;
+            let mut new_mask = SigSet(blocked.0 | action.mask.0);
+            new_mask.add(signum);
+            SignalDisposition::EnterHandler {
+                signum,
+                handler,
+                new_sp,
+                prev_mask: *blocked,
+                new_mask,
+            }
+        }
+    }
+}
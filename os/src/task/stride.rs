@@ -0,0 +1,34 @@
+//! Stride-scheduling helpers.
+//!
+//! The scheduler always dispatches the ready task with the smallest
+//! `stride`, then advances that task's stride by `BIG_STRIDE / priority`.
+//! Because every priority is `>= 2`, `stride_step <= BIG_STRIDE / 2`, which
+//! keeps `max(stride) - min(stride) <= BIG_STRIDE` across all ready tasks;
+//! that invariant is what makes the wraparound-tolerant comparison below
+//! correct.
+//!
+//! This module only has the two primitives (`stride_less`, `stride_step`);
+//! the ready-queue itself lives in the task manager outside this repo
+//! snapshot, and it's that `fetch()` which actually has to call
+//! `stride_less` to pick the minimum and then bump the winner's `stride` by
+//! `stride_step(priority)` before returning it. Neither of those call sites
+//! can be added from here.
+
+/// Large constant divided by priority to get a task's per-dispatch stride
+/// increment; bigger than any single `stride_step` so lower-priority tasks
+/// still make visible progress between dispatches.
+pub const BIG_STRIDE: u64 = 0x10000;
+
+/// Computes the per-dispatch stride increment for a task with `priority`.
+/// `priority` must be `>= 2`, as already enforced by `sys_set_priority`.
+pub fn stride_step(priority: usize) -> u64 {
+    BIG_STRIDE / priority as u64
+}
+
+/// Returns whether `a` should be considered "less than" `b` for scheduling
+/// purposes, tolerating `u64` wraparound. Given the invariant above, the
+/// true difference always fits in `i64`, so a wrapping subtraction
+/// reinterpreted as signed recovers the correct ordering even across a wrap.
+pub fn stride_less(a: u64, b: u64) -> bool {
+    (a.wrapping_sub(b) as i64) < 0
+}
@@ -1,4 +1,11 @@
 //! Types related to task management
+//!
+//! `TaskControlBlock` here is the plain, `Copy`-able per-task record used by
+//! the early syscall-counting/timer bookkeeping (`TaskInfo` et al.); it is
+//! not the `Arc`-shared, `inner_exclusive_access()`-guarded control block
+//! that `current_task()` hands out elsewhere (that type's `TaskControlBlockInner`
+//! already carries `res`/`priority` and is where per-task signal, stride and
+//! rusage state belongs instead).
 
 use super::TaskContext;
 use crate::config::MAX_SYSCALL_NUM;